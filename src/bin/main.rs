@@ -1,9 +1,23 @@
 use std::{
-    io::prelude::*,
+    io::{prelude::*, ErrorKind},
     net::{TcpListener, TcpStream},
+    sync::Arc,
+    time::Duration,
 };
 use threadpool::ThreadPool;
-use web_server::Response;
+use web_server::{response, Request, Router};
+
+/// How long a connection may sit idle waiting for the next request before
+/// it's dropped, so a dead or slow peer can't pin a threadpool worker forever.
+const IDLE_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How much to grow the request buffer by on each `read`, chosen to cover a
+/// typical browser header block (700B-2KB) in a single syscall.
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Hard cap on the header block size, so a client that never sends `\r\n\r\n`
+/// can't make us grow the buffer forever.
+const MAX_HEADERS_SIZE: usize = 64 * 1024;
 
 fn main() {
     let num_of_cpus = (num_cpus::get() as f64 * 0.8) as usize;
@@ -11,6 +25,7 @@ fn main() {
     let addr = "127.0.0.1:3000";
     let listener = TcpListener::bind(addr).unwrap();
     let pool = ThreadPool::new(num_of_cpus);
+    let router = Arc::new(Router::new());
 
     println!(
         "Listening on http://{} , running on {} threads 🚀",
@@ -19,22 +34,103 @@ fn main() {
 
     for stream in listener.incoming() {
         let stream = stream.expect("Couldn't establish a socket connecton!");
+        let router = Arc::clone(&router);
 
-        pool.execute(|| {
-            handle_connection(stream);
+        pool.execute(move || {
+            handle_connection(stream, router);
         });
     }
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
+fn handle_connection(mut stream: TcpStream, router: Arc<Router>) {
     stream
-        .read(&mut buffer)
-        .expect("Couldn't read the `TcpStream` buffer!");
+        .set_read_timeout(Some(IDLE_READ_TIMEOUT))
+        .expect("Couldn't set the idle read timeout!");
 
-    let res = Response::new(&buffer).get_page();
+    loop {
+        let buffer = match read_request(&mut stream) {
+            Ok(Some(buffer)) => buffer,
+            Ok(None) => return,
+            Err(res) => {
+                let _ = stream.write_all(&res);
+                return;
+            }
+        };
 
-    stream
-        .write_all(res.as_bytes())
-        .expect("Couldn't write all bytes to the stream!");
+        let res = router.handle(&buffer);
+
+        stream
+            .write_all(&res)
+            .expect("Couldn't write all bytes to the stream!");
+
+        let keep_alive = Request::new(&buffer)
+            .parse()
+            .map(|req| req.keep_alive())
+            .unwrap_or(false);
+
+        if !keep_alive {
+            return;
+        }
+    }
+}
+
+/// Reads one full HTTP request (headers, then exactly `Content-Length` more
+/// bytes of body) off `stream`, growing the buffer as it goes instead of
+/// truncating at a fixed size. Returns `Ok(None)` once the peer has closed
+/// the connection or gone idle, and `Err` with a ready-to-send response if
+/// the header block exceeds `MAX_HEADERS_SIZE` (413) or can't be parsed (400).
+fn read_request(stream: &mut TcpStream) -> Result<Option<Vec<u8>>, Vec<u8>> {
+    let mut buffer = Vec::with_capacity(READ_CHUNK_SIZE);
+    let mut chunk = [0; READ_CHUNK_SIZE];
+    let mut headers_end = None;
+
+    loop {
+        match stream.read(&mut chunk) {
+            Ok(0) => return Ok(if buffer.is_empty() { None } else { Some(buffer) }),
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(None)
+            }
+            Err(_) => return Ok(None),
+        }
+
+        if headers_end.is_none() {
+            headers_end = find_headers_end(&buffer);
+
+            if headers_end.is_none() && buffer.len() > MAX_HEADERS_SIZE {
+                return Err(response(413, "Payload Too Large", &[], b""));
+            }
+        }
+
+        if let Some(end) = headers_end {
+            let headers_req = match Request::new(&buffer[..end]).parse() {
+                Some(req) => req,
+                None => return Err(response(400, "Bad ass Request", &[], b"")),
+            };
+
+            if headers_req.is_chunked() {
+                // Chunked bodies carry their own termination, so keep
+                // reading until the whole request (and thus the body) can
+                // be fully parsed rather than comparing against a length.
+                if Request::new(&buffer).parse().is_some() {
+                    return Ok(Some(buffer));
+                }
+            } else {
+                let content_length = headers_req.content_length().unwrap_or(0);
+
+                if buffer.len() >= end + content_length {
+                    return Ok(Some(buffer));
+                }
+            }
+        }
+    }
+}
+
+/// Finds the index right after the `\r\n\r\n` header/body separator, if the
+/// buffer contains one yet.
+fn find_headers_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
 }
@@ -0,0 +1,7 @@
+pub mod method;
+pub mod req_res;
+pub mod router;
+
+pub use method::Method;
+pub use req_res::{response, ParsedRequest, Request, Response};
+pub use router::Router;
@@ -0,0 +1,48 @@
+use std::str::FromStr;
+
+/// The HTTP request methods this server understands. A request line whose
+/// method doesn't map to one of these is rejected with a `405 Method Not
+/// Allowed` rather than treated as `GET`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Head,
+    Options,
+    Patch,
+}
+
+impl FromStr for Method {
+    type Err = ();
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => Ok(Method::Get),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "DELETE" => Ok(Method::Delete),
+            "HEAD" => Ok(Method::Head),
+            "OPTIONS" => Ok(Method::Options),
+            "PATCH" => Ok(Method::Patch),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_known_methods_case_insensitively() {
+        assert_eq!("get".parse(), Ok(Method::Get));
+        assert_eq!("POST".parse(), Ok(Method::Post));
+    }
+
+    #[test]
+    fn it_rejects_unknown_methods() {
+        assert_eq!("TRACE".parse::<Method>(), Err(()));
+    }
+}
@@ -1,7 +1,9 @@
-use std::fs;
+use std::{fs, time::SystemTime};
 
 use indexmap::IndexMap;
 
+use crate::Method;
+
 /// `Response` is a struct that takes the `buffer` from `TcpStream`
 /// and can call methods on it to respond to the requester through different ways.
 pub struct Response<'a> {
@@ -26,6 +28,60 @@ pub struct ParsedRequest {
     body: String,
 }
 
+impl ParsedRequest {
+    /// The request's target path, e.g. `/about.html`.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The typed request method, or `None` if the request line's method
+    /// token doesn't map to a known `Method` variant.
+    pub fn method(&self) -> Option<Method> {
+        self.method.parse().ok()
+    }
+
+    /// Looks up a header by name, ignoring case, since header names are
+    /// case-insensitive per the HTTP spec but `IndexMap` keys are not.
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Whether the request body is framed with `Transfer-Encoding: chunked`
+    /// rather than a `Content-Length`.
+    pub fn is_chunked(&self) -> bool {
+        self.header("Transfer-Encoding")
+            .map(|v| v.split(',').any(|e| e.trim().eq_ignore_ascii_case("chunked")))
+            .unwrap_or(false)
+    }
+
+    /// Parses the `Content-Length` header, if present, so callers know how
+    /// many body bytes to read off the socket after the header block.
+    pub fn content_length(&self) -> Option<usize> {
+        self.header("Content-Length")?.trim().parse().ok()
+    }
+
+    /// Decides whether the underlying `TcpStream` should be kept open to
+    /// serve further requests, following the HTTP/1.0 vs HTTP/1.1 defaults:
+    /// HTTP/1.1 connections are persistent unless `Connection: close` is
+    /// present, while HTTP/1.0 connections are closed unless the client
+    /// opts in with `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("Connection").unwrap_or("");
+        let wants_close = connection
+            .split(',')
+            .any(|token| token.trim().eq_ignore_ascii_case("close"));
+        let wants_keep_alive = connection
+            .split(',')
+            .any(|token| token.trim().eq_ignore_ascii_case("keep-alive"));
+
+        if self.http_version >= 1.1 {
+            !wants_close
+        } else {
+            wants_keep_alive
+        }
+    }
+}
+
 impl<'a> Request<'a> {
     /// Instantiate a new `Request` struct by providing a request buffer data as the only argument.
     pub fn new(buffer: &'a [u8]) -> Request<'a> {
@@ -43,36 +99,41 @@ impl<'a> Request<'a> {
     /// - headers
     /// - body
     pub fn parse(&self) -> Option<ParsedRequest> {
-        let req_str = String::from_utf8_lossy(self.buffer);
-
-        let mut headers = IndexMap::new();
-        let mut body = String::from("");
+        let (header_bytes, raw_body) = split_headers_and_body(self.buffer);
+        let header_str = String::from_utf8_lossy(header_bytes);
 
-        let mut lines: Vec<&str> = req_str.lines().collect();
+        let mut lines: Vec<&str> = header_str.lines().collect();
 
         let mut parts = lines[0].split(' ');
 
         let method = parts.next()?.to_string();
         let uri = parts.next()?.to_string();
-        let http_version = parts
-            .next()?
-            .replace("HTTP/", "")
-            .parse()
-            .expect("Couldn't parse http version!");
+        let http_version = parts.next()?.replace("HTTP/", "").parse().ok()?;
 
         lines.remove(0);
 
-        for (idx, &i) in lines.iter().enumerate() {
-            if i.is_empty() {
-                body = lines[idx + 1].trim().replace("\u{0}", "");
-            }
-
+        let mut headers = IndexMap::new();
+        for i in lines {
             let pair: Vec<&str> = i.split(':').map(|e| e.trim()).collect();
             if pair.len() >= 2 {
                 headers.insert(pair[0].to_string(), pair[1].to_string());
             }
         }
 
+        let is_chunked = find_header(&headers, "Transfer-Encoding")
+            .map(|v| v.split(',').any(|e| e.trim().eq_ignore_ascii_case("chunked")))
+            .unwrap_or(false);
+
+        // A chunked body carries its own framing, so `Content-Length` (if a
+        // client sent one anyway) must not be trusted.
+        let body = if is_chunked {
+            let (decoded, trailers) = decode_chunked(raw_body)?;
+            headers.extend(trailers);
+            String::from_utf8_lossy(&decoded).replace('\u{0}', "")
+        } else {
+            String::from_utf8_lossy(raw_body).replace('\u{0}', "")
+        };
+
         Some(ParsedRequest {
             body,
             headers,
@@ -83,6 +144,89 @@ impl<'a> Request<'a> {
     }
 }
 
+/// Looks up a header by name, ignoring case, in a not-yet-wrapped header map.
+/// Shared by `ParsedRequest::header` and `Request::parse` (which needs to
+/// check `Transfer-Encoding` before a `ParsedRequest` exists).
+fn find_header<'h>(headers: &'h IndexMap<String, String>, name: &str) -> Option<&'h str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Splits a raw request buffer into its header block and the bytes that
+/// follow the blank line, without the lossy text-line round-trip that would
+/// corrupt a binary (e.g. chunked) body. Falls back to a bare `\n\n`
+/// separator for requests assembled from `str` literals in tests.
+fn split_headers_and_body(buffer: &[u8]) -> (&[u8], &[u8]) {
+    if let Some(idx) = find_subslice(buffer, b"\r\n\r\n") {
+        (&buffer[..idx], &buffer[idx + 4..])
+    } else if let Some(idx) = find_subslice(buffer, b"\n\n") {
+        (&buffer[..idx], &buffer[idx + 2..])
+    } else {
+        (buffer, &[])
+    }
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Reassembles a chunked-transfer-encoded body from its chunk framing:
+/// `<size-in-hex>[;ext...]\r\n<size bytes>\r\n`, repeated until a zero-size
+/// chunk, optionally followed by trailer headers terminated by a blank
+/// line. Returns `None` on any malformed chunk size or truncated framing.
+fn decode_chunked(mut bytes: &[u8]) -> Option<(Vec<u8>, IndexMap<String, String>)> {
+    let mut decoded = Vec::new();
+
+    loop {
+        let line_end = find_subslice(bytes, b"\r\n")?;
+        let size_str = std::str::from_utf8(&bytes[..line_end])
+            .ok()?
+            .split(';')
+            .next()?
+            .trim();
+        let size = usize::from_str_radix(size_str, 16).ok()?;
+        bytes = &bytes[line_end + 2..];
+
+        if size == 0 {
+            let trailers = decode_trailers(bytes)?;
+            return Some((decoded, trailers));
+        }
+
+        let chunk_end = size.checked_add(2)?;
+        if bytes.len() < chunk_end || &bytes[size..chunk_end] != b"\r\n" {
+            return None;
+        }
+
+        decoded.extend_from_slice(&bytes[..size]);
+        bytes = &bytes[size + 2..];
+    }
+}
+
+/// Consumes the optional trailer headers after the final chunk, up to and
+/// including the terminating blank line.
+fn decode_trailers(mut bytes: &[u8]) -> Option<IndexMap<String, String>> {
+    let mut trailers = IndexMap::new();
+
+    loop {
+        let line_end = find_subslice(bytes, b"\r\n")?;
+        let line = std::str::from_utf8(&bytes[..line_end]).ok()?;
+        bytes = &bytes[line_end + 2..];
+
+        if line.is_empty() {
+            return Some(trailers);
+        }
+
+        let pair: Vec<&str> = line.splitn(2, ':').map(|e| e.trim()).collect();
+        if pair.len() == 2 {
+            trailers.insert(pair[0].to_string(), pair[1].to_string());
+        }
+    }
+}
+
 impl<'a> Response<'a> {
     /// Instantiate a new `Response` struct by providing a request buffer data as the only argument.
     pub fn new(buffer: &'a [u8]) -> Response<'a> {
@@ -98,11 +242,12 @@ impl<'a> Response<'a> {
     /// that the request was malformed.
     ///
     /// Then it reads the corresponding HTML file from the file system and returns back a well-formatted
-    /// response string with the status code and the page contents.
-    pub fn get_page(&self) -> String {
+    /// response: compressed when the client's `Accept-Encoding` allows it, and answered with a bare
+    /// `304 Not Modified` when the client's cache (`If-None-Match`/`If-Modified-Since`) is already fresh.
+    pub fn get_page(&self) -> Vec<u8> {
         let req = match Request::new(self.buffer).parse() {
             Some(v) => v,
-            None => return response(400, "Bad ass Request", "", ""),
+            None => return response(400, "Bad ass Request", &[], b""),
         };
 
         let page_path = if req.uri == "/" {
@@ -111,7 +256,10 @@ impl<'a> Response<'a> {
             format!(
                 "frontend{}{}",
                 req.uri,
-                if req.uri.contains(".html") {
+                // Only extensionless routes (e.g. `/about`) are shorthand for
+                // an HTML page; `/app.css`, `/logo.png`, etc. already name a
+                // real static asset and must not get `.html` tacked on.
+                if req.uri.rsplit('/').next().unwrap_or("").contains('.') {
                     ""
                 } else {
                     ".html"
@@ -119,26 +267,209 @@ impl<'a> Response<'a> {
             )
         };
 
-        let content = fs::read_to_string(page_path)
-            .unwrap_or_else(|_| fs::read_to_string("frontend/404.html").unwrap());
+        let (served_path, content) = match fs::read(&page_path) {
+            Ok(content) => (page_path, content),
+            Err(_) => (
+                String::from("frontend/404.html"),
+                fs::read("frontend/404.html").unwrap(),
+            ),
+        };
+
+        let connection = if req.keep_alive() {
+            "keep-alive"
+        } else {
+            "close"
+        };
+
+        let metadata = fs::metadata(&served_path).ok();
+        let etag = metadata.as_ref().map(etag_for);
+        let last_modified = metadata.as_ref().and_then(|m| m.modified().ok());
+
+        if let Some(etag) = &etag {
+            if is_not_modified(&req, etag, last_modified) {
+                return response(
+                    304,
+                    "Not Modified",
+                    &[("ETag", etag), ("Connection", connection)],
+                    b"",
+                );
+            }
+        }
+
+        let accept_encoding = req.header("Accept-Encoding").unwrap_or("");
+        let may_compress = content.len() >= MIN_COMPRESSIBLE_SIZE && is_compressible(&served_path);
+        let compressed = if may_compress {
+            negotiate_encoding(accept_encoding)
+                .and_then(|encoding| compress(encoding, &content).map(|bytes| (encoding, bytes)))
+        } else {
+            None
+        };
+
+        let (body, content_encoding) = match compressed {
+            Some((encoding, bytes)) => (bytes, Some(encoding)),
+            None => (content, None),
+        };
+
+        let content_length = body.len().to_string();
+        let content_type = content_type_for(&served_path);
+        let last_modified_str = last_modified.map(httpdate::fmt_http_date);
+
+        let mut headers = vec![
+            ("Content-Length", content_length.as_str()),
+            ("Connection", connection),
+            ("Content-Type", content_type),
+        ];
+        if let Some(encoding) = content_encoding {
+            headers.push(("Content-Encoding", encoding));
+        }
+        // Tells caches the body depends on the request's `Accept-Encoding`,
+        // so a cache keyed only on the URL can't hand a compressed body to a
+        // client that never negotiated it (or vice versa).
+        if may_compress {
+            headers.push(("Vary", "Accept-Encoding"));
+        }
+        if let Some(etag) = &etag {
+            headers.push(("ETag", etag));
+        }
+        if let Some(last_modified_str) = &last_modified_str {
+            headers.push(("Last-Modified", last_modified_str));
+        }
+
+        response(200, "OK", &headers, &body)
+    }
+}
+
+/// Infers a `Content-Type` from a file's extension, falling back to a
+/// generic binary type for anything unrecognized.
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or("") {
+        "html" => "text/html",
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Derives a weak `ETag` from a file's size and modification time, cheap
+/// enough to compute on every request without hashing the file contents.
+fn etag_for(metadata: &fs::Metadata) -> String {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    format!("W/\"{:x}-{:x}\"", metadata.len(), mtime)
+}
+
+/// Whether the client's cache is already fresh: when `If-None-Match` is
+/// present it alone decides (fresh only if it lists `etag`), otherwise an
+/// `If-Modified-Since` no older than the file's modification time counts as
+/// fresh.
+fn is_not_modified(req: &ParsedRequest, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = req.header("If-None-Match") {
+        return if_none_match.split(',').any(|tag| tag.trim() == etag);
+    }
+
+    if let (Some(if_modified_since), Some(last_modified)) =
+        (req.header("If-Modified-Since"), last_modified)
+    {
+        if let Ok(since) = httpdate::parse_http_date(if_modified_since) {
+            return last_modified <= since;
+        }
+    }
+
+    false
+}
+
+/// Files smaller than this aren't worth the CPU cost of compressing, since
+/// gzip/deflate framing overhead can make tiny payloads larger, not smaller.
+const MIN_COMPRESSIBLE_SIZE: usize = 1024;
+
+/// Whether a file extension is text-like enough to benefit from compression.
+/// Image/font/archive formats served from `frontend/` are already compressed,
+/// so re-compressing them just burns CPU for no size benefit.
+fn is_compressible(path: &str) -> bool {
+    let ext = path.rsplit('.').next().unwrap_or("");
+    matches!(ext, "html" | "css" | "js" | "json" | "xml" | "svg" | "txt")
+}
+
+/// Picks an encoding from `Accept-Encoding` that this server supports, in a
+/// fixed order of preference: gzip, then deflate, then brotli. Each token may
+/// carry a `;q=...` weight (e.g. `gzip;q=0.9`), which is stripped before
+/// comparing; a token explicitly weighted `q=0` is treated as refused.
+fn negotiate_encoding(accept_encoding: &str) -> Option<&'static str> {
+    let accepted: Vec<&str> = accept_encoding
+        .split(',')
+        .filter_map(|token| {
+            let mut parts = token.split(';');
+            let name = parts.next()?.trim();
+            let refused = parts.any(|param| param.trim() == "q=0" || param.trim() == "q=0.0");
+
+            (!name.is_empty() && !refused).then_some(name)
+        })
+        .collect();
+
+    ["gzip", "deflate", "br"]
+        .into_iter()
+        .find(|candidate| accepted.iter().any(|e| e.eq_ignore_ascii_case(candidate)))
+}
+
+/// Compresses `content` with the given `Content-Encoding` name.
+fn compress(encoding: &str, content: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
 
-        response(
-            200,
-            "OK",
-            &format!("Content-Length: {}", content.len()),
-            &content,
-        )
+    match encoding {
+        "gzip" => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content).ok()?;
+            encoder.finish().ok()
+        }
+        "deflate" => {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content).ok()?;
+            encoder.finish().ok()
+        }
+        "br" => {
+            let mut output = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 5, 22);
+                encoder.write_all(content).ok()?;
+            }
+            Some(output)
+        }
+        _ => None,
     }
 }
 
-/// A useful helper function for formatting the response string for easy re-use
-/// It constructs a well-formatted response string using the provided arguments
-/// `status`, `desc`, `headers` and the `body` of the response.
-pub fn response(status: i32, desc: &str, headers: &str, body: &str) -> String {
-    format!(
-        "HTTP/1.1 {} {}\r\n{}\r\n\r\n{}",
-        status, desc, headers, body
-    )
+/// A useful helper function for formatting the response bytes for easy re-use.
+/// It constructs a well-formatted response using the provided arguments
+/// `status`, `desc`, `headers` and the `body` of the response. `headers` is a
+/// list of `(name, value)` pairs rather than a single pre-joined string, so
+/// callers can attach several headers (e.g. `Content-Encoding` alongside
+/// `Content-Length`) without formatting them by hand. `body` is raw bytes
+/// since a compressed body isn't valid UTF-8.
+pub fn response(status: i32, desc: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut res = format!("HTTP/1.1 {} {}\r\n", status, desc).into_bytes();
+
+    for (name, value) in headers {
+        res.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+
+    res.extend_from_slice(b"\r\n");
+    res.extend_from_slice(body);
+
+    res
 }
 
 #[cfg(test)]
@@ -171,16 +502,70 @@ Cache-Control: max-age=0";
     fn it_responds_correctly() {
         let res = Response::new(SAMPLE_REQ_STR.as_bytes());
 
-        res.get_page().starts_with("HTTP/1.1 200 OK\r\n");
+        assert!(res.get_page().starts_with(b"HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn it_returns_not_modified_when_the_etag_matches() {
+        let res = Response::new(SAMPLE_REQ_STR.as_bytes()).get_page();
+        let res = String::from_utf8_lossy(&res);
+        let etag = res
+            .lines()
+            .find_map(|line| line.strip_prefix("ETag: "))
+            .unwrap()
+            .trim();
+
+        let req_str = format!("{}\r\nIf-None-Match: {}\r\n\r\n", SAMPLE_REQ_STR, etag);
+        let res = Response::new(req_str.as_bytes()).get_page();
+
+        assert!(res.starts_with(b"HTTP/1.1 304 Not Modified\r\n"));
+    }
+
+    #[test]
+    fn it_negotiates_encoding_with_q_values() {
+        assert_eq!(negotiate_encoding("gzip;q=0.9, deflate;q=0.1"), Some("gzip"));
+        assert_eq!(negotiate_encoding("gzip;q=0, deflate"), Some("deflate"));
+        assert_eq!(negotiate_encoding("gzip;q=0"), None);
+    }
+
+    #[test]
+    fn it_infers_content_type_from_extension() {
+        assert_eq!(content_type_for("frontend/index.html"), "text/html");
+        assert_eq!(content_type_for("frontend/app.js"), "application/javascript");
+        assert_eq!(content_type_for("frontend/logo.png"), "image/png");
     }
 
     #[test]
     fn it_refuses_invalid_requests() {
-        let req_str = SAMPLE_REQ_STR.replace("/ HTTP/1.1", "/not-found HTTP/1.1");
+        let req_str = SAMPLE_REQ_STR.replace("HTTP/1.1", "HTTP/x");
         let res = Response::new(req_str.as_bytes());
 
-        res.get_page()
-            .starts_with("HTTP/1.1 400 Bad ass Request\r\n");
+        assert!(res
+            .get_page()
+            .starts_with(b"HTTP/1.1 400 Bad ass Request\r\n"));
+    }
+
+    #[test]
+    fn it_returns_none_instead_of_panicking_on_a_malformed_http_version() {
+        let req_str = SAMPLE_REQ_STR.replace("HTTP/1.1", "HTTP/x");
+
+        assert!(Request::new(req_str.as_bytes()).parse().is_none());
+    }
+
+    #[test]
+    fn it_decodes_chunked_request_bodies() {
+        let req_str = "POST /upload HTTP/1.1\r\nHost: 127.0.0.1:3000\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n";
+        let req = Request::new(req_str.as_bytes()).parse().unwrap();
+
+        assert_eq!(req.body, "Wikipedia");
+    }
+
+    #[test]
+    fn it_rejects_malformed_chunk_sizes() {
+        let req_str =
+            "POST /upload HTTP/1.1\r\nHost: 127.0.0.1:3000\r\nTransfer-Encoding: chunked\r\n\r\nnot-hex\r\ndata\r\n0\r\n\r\n";
+
+        assert!(Request::new(req_str.as_bytes()).parse().is_none());
     }
 
     #[test]
@@ -189,15 +574,15 @@ Cache-Control: max-age=0";
             response(
                 200,
                 "OK",
-                "Content-Type: application/json",
-                "{ logged_in: true }"
+                &[("Content-Type", "application/json")],
+                b"{ logged_in: true }"
             ),
-            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{ logged_in: true }"
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{ logged_in: true }".to_vec()
         );
 
         assert_eq!(
-            response(400, "Bad ass", "", ""),
-            "HTTP/1.1 400 Bad ass\r\n\r\n\r\n"
+            response(400, "Bad ass", &[], b""),
+            b"HTTP/1.1 400 Bad ass\r\n\r\n".to_vec()
         );
     }
 }
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::{response, Method, ParsedRequest, Request, Response};
+
+type Handler = Box<dyn Fn(&ParsedRequest) -> Vec<u8> + Send + Sync>;
+
+/// Dispatches parsed requests to handler closures registered by `(Method,
+/// path)`, so application code can run real logic instead of the crate only
+/// ever being a static file dumper. Unmatched `GET` requests fall back to
+/// `Response::get_page`'s static-file serving, since that's the crate's
+/// original behavior; anything else unmatched is a `404`, and a request
+/// whose method doesn't parse into a known `Method` is a `405`.
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<(Method, String), Handler>,
+}
+
+impl Router {
+    /// Instantiate an empty `Router` with no registered routes.
+    pub fn new() -> Router {
+        Router {
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Registers `handler` to run for requests matching `method` and `path`
+    /// exactly, overwriting any handler already registered for that pair.
+    pub fn route(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl Fn(&ParsedRequest) -> Vec<u8> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.routes
+            .insert((method, path.to_string()), Box::new(handler));
+        self
+    }
+
+    /// Parses `buffer` as an HTTP request and dispatches it: a registered
+    /// route takes precedence, an unmatched `GET` falls back to serving a
+    /// static file from `frontend/`, and anything else is a `404`. A buffer
+    /// that doesn't parse is a `400`, and an unknown method is a `405`.
+    pub fn handle(&self, buffer: &[u8]) -> Vec<u8> {
+        let req = match Request::new(buffer).parse() {
+            Some(req) => req,
+            None => return response(400, "Bad ass Request", &[], b""),
+        };
+
+        let method = match req.method() {
+            Some(method) => method,
+            None => return response(405, "Method Not Allowed", &[("Content-Length", "0")], b""),
+        };
+
+        if let Some(handler) = self.routes.get(&(method, req.uri().to_string())) {
+            return handler(&req);
+        }
+
+        if method == Method::Get {
+            return Response::new(buffer).get_page();
+        }
+
+        response(404, "Not Found", &[("Content-Length", "0")], b"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prefers_a_registered_route_over_the_static_fallback() {
+        let mut router = Router::new();
+        router.route(Method::Get, "/ping", |_req| {
+            response(200, "OK", &[], b"pong")
+        });
+
+        let res = router.handle(b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert!(res.ends_with(b"pong"));
+    }
+
+    #[test]
+    fn it_rejects_unknown_methods_with_405() {
+        let router = Router::new();
+
+        let res = router.handle(b"TRACE / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert!(res.starts_with(b"HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[test]
+    fn it_404s_unmatched_non_get_routes() {
+        let router = Router::new();
+
+        let res = router.handle(b"POST /upload HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert!(res.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    }
+}